@@ -0,0 +1,192 @@
+use std::error::Error;
+use tokio::prelude::*;
+use tokio::net::TcpStream;
+
+use crate::redis::{Command, ParseError, RedisValue};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Initial read buffer size: two 4 KiB pages, which comfortably holds most
+/// replies without growing.
+const INITIAL_BUF_SIZE: usize = 8 * 1024;
+
+/// A buffered, incremental RESP reader over a `TcpStream`.
+///
+/// Frames are read into a buffer and decoded with
+/// [`RedisValue::deserialize`]. A frame that straddles two reads (or two TCP
+/// segments) is never lost: the unparsed tail is moved to the front of the
+/// buffer and combined with the next read instead of being dropped or
+/// causing a panic. The buffer only grows when a single frame fills it.
+pub struct Connection {
+    stream: TcpStream,
+    buf: Vec<u8>,
+    /// Bytes `buf[..filled]` hold data read from the socket; of these,
+    /// `buf[start..filled]` hasn't been consumed by the caller yet.
+    filled: usize,
+    /// Bytes `buf[..start]` have already been decoded into values and are
+    /// free to be overwritten.
+    start: usize,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> Connection {
+        Connection {
+            stream,
+            buf: vec![0u8; INITIAL_BUF_SIZE],
+            filled: 0,
+            start: 0,
+        }
+    }
+
+    pub async fn connect(hostname: &str, port: u16) -> Result<Connection> {
+        let stream = TcpStream::connect((hostname, port)).await?;
+        Ok(Connection::new(stream))
+    }
+
+    /// Write raw bytes (e.g. an already-encoded `RedisValue`) to the socket.
+    pub async fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+        self.stream.write_all(data).await?;
+        Ok(())
+    }
+
+    /// Read and decode the next complete `RedisValue`, blocking on more
+    /// socket reads until a whole frame is available.
+    pub async fn next_value(&mut self) -> Result<RedisValue> {
+        loop {
+            match RedisValue::deserialize(&self.buf[self.start..self.filled]) {
+                Ok((value, consumed)) => {
+                    self.start += consumed;
+                    return Ok(value)
+                },
+                Err(ParseError::Incomplete) => {},
+                Err(ParseError::Invalid(msg)) => return Err(msg.into()),
+            }
+
+            // Compact: slide the unparsed tail down to the front of the
+            // buffer so the next read has room to land.
+            if self.start > 0 {
+                self.buf.copy_within(self.start..self.filled, 0);
+                self.filled -= self.start;
+                self.start = 0;
+            }
+
+            // Only grow the buffer if a single frame doesn't fit in it at all.
+            if self.filled == self.buf.len() {
+                self.buf.resize(self.buf.len() * 2, 0);
+            }
+
+            let n = self.stream.read(&mut self.buf[self.filled..]).await?;
+            if n == 0 {
+                return Err("connection closed by peer".into())
+            }
+            self.filled += n;
+        }
+    }
+
+    /// Send a command and wait for its reply.
+    pub async fn run(&mut self, cmd: RedisValue) -> Result<RedisValue> {
+        self.write_raw(cmd.to_wire()?.as_slice()).await?;
+        self.next_value().await
+    }
+
+    pub async fn get(&mut self, key: &str) -> Result<RedisValue> {
+        self.run(RedisValue::from_vec(vec!["GET".to_string(), key.to_string()])).await
+    }
+
+    pub async fn set(&mut self, key: &str, val: &str) -> Result<RedisValue> {
+        self.run(RedisValue::from_vec(vec!["SET".to_string(), key.to_string(), val.to_string()])).await
+    }
+
+    /// Negotiate the RESP protocol version with `HELLO`.
+    pub async fn hello(&mut self, protover: Option<u8>) -> Result<RedisValue> {
+        self.run(RedisValue::from(Command::Hello(protover))).await
+    }
+
+    pub async fn subscribe(&mut self, channel: &str) -> Result<RedisValue> {
+        self.run(RedisValue::from_vec(vec!["SUBSCRIBE".to_string(), channel.to_string()])).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    /// Spawn a one-shot mock RESP server on an ephemeral port: it accepts a
+    /// single client and writes `chunks` to it back-to-back, with a small
+    /// delay between each so they land as separate reads rather than
+    /// coalescing into one.
+    async fn mock_server(chunks: Vec<Vec<u8>>) -> SocketAddr {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("local_addr");
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.expect("accept");
+            for chunk in chunks {
+                socket.write_all(&chunk).await.expect("write_all");
+                tokio::time::delay_for(Duration::from_millis(5)).await;
+            }
+        });
+
+        addr
+    }
+
+    async fn connect(addr: SocketAddr) -> Connection {
+        Connection::connect(&addr.ip().to_string(), addr.port()).await.expect("connect")
+    }
+
+    #[tokio::test]
+    async fn test_decodes_multiple_values_from_one_oversized_read() {
+        let data = b"+one\r\n+two\r\n+three\r\n".to_vec();
+        let addr = mock_server(vec![data]).await;
+        let mut conn = connect(addr).await;
+
+        for expected in &["one", "two", "three"] {
+            match conn.next_value().await.expect("next_value") {
+                RedisValue::Str(s) => assert_eq!(&s, expected),
+                other => panic!("expected Str({:?}), got {:?}", expected, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reassembles_frame_split_mid_array() {
+        let data = b"*2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n";
+        // split partway through the second bulk string's length header
+        let (head, tail) = data.split_at(15);
+        let addr = mock_server(vec![head.to_vec(), tail.to_vec()]).await;
+        let mut conn = connect(addr).await;
+
+        match conn.next_value().await.expect("next_value") {
+            RedisValue::Array(items) => {
+                assert_eq!(items.len(), 2);
+                assert_eq!(items[0].as_str().unwrap().as_ref(), "foo");
+                assert_eq!(items[1].as_str().unwrap().as_ref(), "bar");
+            },
+            other => panic!("expected Array, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reassembles_multibyte_utf8_split_across_reads() {
+        // "h" + U+00E9 ("\xC3\xA9") + "llo", split between the two bytes of
+        // the multibyte character.
+        let payload = "h\u{e9}llo".as_bytes().to_vec();
+        let mut data = format!("${}\r\n", payload.len()).into_bytes();
+        data.extend_from_slice(&payload);
+        data.extend_from_slice(b"\r\n");
+
+        // prefix ("$6\r\n") plus the first two payload bytes ("h", 0xC3)
+        let split_at = data.len() - payload.len();
+        let (head, tail) = data.split_at(split_at);
+        let addr = mock_server(vec![head.to_vec(), tail.to_vec()]).await;
+        let mut conn = connect(addr).await;
+
+        match conn.next_value().await.expect("next_value") {
+            RedisValue::Bulk(b) => assert_eq!(b.as_ref(), payload.as_slice()),
+            other => panic!("expected Bulk, got {:?}", other),
+        }
+    }
+}
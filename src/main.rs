@@ -1,13 +1,17 @@
 use structopt::StructOpt;
 use tokio::prelude::*;
-use tokio::net::TcpStream;
 use rustyline::{Editor, error::ReadlineError};
 use std::error::Error;
 use log::*;
 use std::io::Write;
 
 mod redis;
+mod connection;
+mod pool;
+mod proxy;
 use self::redis::*;
+use self::connection::Connection;
+use self::pool::ConnectionPool;
 
 #[derive(Debug, StructOpt)]
 struct Opt {
@@ -23,53 +27,106 @@ struct Opt {
     #[structopt(short, long)]
     pub pipe: bool,
 
+    /// Listen on this address and forward every client to hostname:port,
+    /// logging each command/reply pair as it passes through.
+    #[structopt(long)]
+    pub proxy: Option<String>,
+
     pub cmds: Vec<String>,
 }
 
-type Result<T> = std::result::Result<T, Box<dyn Error>>;
-
-async fn read_redis_output(cli: &mut TcpStream) -> Result<Vec<u8>> {
-    let mut res = vec![];
-    let mut buf = [0u8; 64];
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
 
-    loop {
-        let n = cli.read(&mut buf[..]).await?;
-        res.extend(&buf[..n]);
-        if n < 64 { break }
+async fn consume_one(conn: &mut Connection) -> Result<()> {
+    let value = conn.next_value().await?;
+    // Out-of-band push messages (pub/sub, client-side caching, ...) are
+    // logged separately so they aren't mistaken for a command's reply.
+    match value {
+        RedisValue::Push(_) => debug!("push: {}", value),
+        _ => info!("{}", value),
     }
-    Ok(res)
+    Ok(())
 }
 
-async fn consume_all_output(cli: &mut TcpStream) -> Result<()> {
-    let res = read_redis_output(cli).await?;
-
-    let mut start = 0;
-    while let Some((value, left)) = RedisValue::deserialize(&res[start..]) {
-        info!("{}", value);
-        start += left;
+/// Negotiate RESP3 via `HELLO 3`, falling back to RESP2 if the server
+/// doesn't understand it (e.g. an older Redis).
+async fn negotiate_hello(conn: &mut Connection) -> Result<()> {
+    match conn.hello(Some(3)).await {
+        Ok(RedisValue::Error(err)) => {
+            debug!("HELLO 3 rejected, staying on RESP2: {}", err);
+        },
+        Ok(value) => {
+            debug!("HELLO 3 negotiated: {}", value);
+        },
+        Err(err) => {
+            debug!("HELLO 3 failed, staying on RESP2: {}", err);
+        },
     }
     Ok(())
 }
 
-async fn stream(args: Vec<String>, pipe: bool, cli: &mut TcpStream) -> Result<()> {
+async fn stream(args: Vec<String>, conn: &mut Connection) -> Result<()> {
     let cmd = args[0].clone();
-    let data = if pipe {
-        args.into_iter().map(|a| a + "\r\n").collect::<String>().into_bytes()
-    } else {
-        let value = RedisValue::from_vec(args);
-        value.to_wire()?
-    };
-    cli.write(data.as_slice()).await?;
-
-    match cmd.as_str() {
-        "monitor" | "subscribe" => loop {
-            consume_all_output(cli).await?
+
+    match (cmd.as_str(), args.len()) {
+        ("get", 2) => {
+            let value = conn.get(&args[1]).await?;
+            info!("{}", value);
+            Ok(())
+        },
+        ("set", 3) => {
+            let value = conn.set(&args[1], &args[2]).await?;
+            info!("{}", value);
+            Ok(())
+        },
+        ("subscribe", 2) => {
+            conn.subscribe(&args[1]).await?;
+            loop {
+                consume_one(conn).await?
+            }
+        },
+        ("monitor", _) => {
+            conn.write_raw(RedisValue::from_vec(args).to_wire()?.as_slice()).await?;
+            loop {
+                consume_one(conn).await?
+            }
+        },
+        _ => {
+            conn.write_raw(RedisValue::from_vec(args).to_wire()?.as_slice()).await?;
+            consume_one(conn).await
         },
-        _ => consume_all_output(cli).await
     }
 }
 
-async fn interactive<S: AsRef<str>>(prompt: S, cli: &mut TcpStream) -> Result<()> {
+/// Maximum number of concurrent connections the pipe mode's pool will open.
+const PIPE_POOL_SIZE: usize = 8;
+
+/// Run each piped-in command concurrently against a `ConnectionPool`, rather
+/// than sequentially over a single connection.
+async fn stream_piped(cmds: Vec<String>, pool: ConnectionPool) -> Result<()> {
+    let handles: Vec<_> = cmds.into_iter()
+        .map(|line| line.split_whitespace().map(|s| s.to_owned()).collect::<Vec<String>>())
+        .filter(|args| !args.is_empty())
+        .map(|args| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let mut conn = pool.get().await?;
+                conn.run(RedisValue::from_vec(args)).await
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        match handle.await {
+            Ok(Ok(value)) => info!("{}", value),
+            Ok(Err(err)) => error!("{}", err),
+            Err(join_err) => error!("{}", join_err),
+        }
+    }
+    Ok(())
+}
+
+async fn interactive<S: AsRef<str>>(prompt: S, conn: &mut Connection) -> Result<()> {
     let mut rl = Editor::<()>::new();
     loop {
         let readline = rl.readline(prompt.as_ref());
@@ -78,16 +135,44 @@ async fn interactive<S: AsRef<str>>(prompt: S, cli: &mut TcpStream) -> Result<()
                 rl.add_history_entry(line.as_str());
                 let args = line.split_whitespace().map(|s| s.to_owned()).collect::<Vec<String>>();
                 let cmd = args[0].clone();
-                let value = RedisValue::from_vec(args);
-                cli.write(value.to_wire()?.as_slice()).await?;
 
-                match cmd.as_str() {
-                    "monitor" | "subscribe" => loop {
-                        consume_all_output(cli).await?
+                match (cmd.as_str(), args.len()) {
+                    ("get", 2) => {
+                        let value = conn.get(&args[1]).await?;
+                        print!("{}", value);
+                    },
+                    ("set", 3) => {
+                        let value = conn.set(&args[1], &args[2]).await?;
+                        print!("{}", value);
+                    },
+                    ("subscribe", 2) => {
+                        conn.subscribe(&args[1]).await?;
+                        loop {
+                            consume_one(conn).await?
+                        }
+                    },
+                    ("monitor", _) => {
+                        conn.write_raw(RedisValue::from_vec(args).to_wire()?.as_slice()).await?;
+                        loop {
+                            consume_one(conn).await?
+                        }
                     },
                     _ => {
-                        let res = read_redis_output(cli).await?;
-                        print!("{}", RedisValue::deserialize(&res).expect("").0);
+                        conn.write_raw(RedisValue::from_vec(args).to_wire()?.as_slice()).await?;
+
+                        // Unsolicited RESP3 pushes can arrive ahead of the
+                        // real reply; log them and keep waiting instead of
+                        // printing one as if it were this command's reply,
+                        // which would desync the REPL from its own
+                        // request/reply pairing for everything typed next.
+                        let value = loop {
+                            let value = conn.next_value().await?;
+                            match value {
+                                RedisValue::Push(_) => debug!("push: {}", value),
+                                _ => break value,
+                            }
+                        };
+                        print!("{}", value);
                     }
                 }
             },
@@ -107,22 +192,28 @@ async fn interactive<S: AsRef<str>>(prompt: S, cli: &mut TcpStream) -> Result<()
     Ok(())
 }
 
-//TODO: add proxy mode
 async fn run(args: Opt) -> Result<()> {
-    let mut cli = TcpStream::connect((args.hostname.as_str(), args.port)).await?;
+    if let Some(listen_addr) = &args.proxy {
+        return Ok(proxy::run(listen_addr, args.hostname.as_str(), args.port).await?);
+    }
+
+    if args.pipe {
+        let mut buf = String::new();
+        tokio::io::stdin().read_to_string(&mut buf).await?;
+        let cmds = buf.split('\n').map(|s| s.to_owned()).collect::<Vec<String>>();
+        let pool = ConnectionPool::new(args.hostname.as_str(), args.port, PIPE_POOL_SIZE);
+        return stream_piped(cmds, pool).await;
+    }
+
+    let mut conn = Connection::connect(args.hostname.as_str(), args.port).await?;
     let prompt = format!("{}:{}> ", args.hostname,args.port);
 
-    if args.cmds.len() == 0 && !args.pipe {
-        interactive(prompt, &mut cli).await
+    negotiate_hello(&mut conn).await?;
+
+    if args.cmds.len() == 0 {
+        interactive(prompt, &mut conn).await
     } else {
-        let cmds = if args.pipe {
-            let mut buf = String::new();
-            tokio::io::stdin().read_to_string(&mut buf).await?;
-            buf.split('\n').map(|s| s.to_owned()).collect::<Vec<String>>()
-        } else {
-            args.cmds
-        };
-        stream(cmds, args.pipe, &mut cli).await
+        stream(args.cmds, &mut conn).await
     }
 }
 
@@ -148,4 +239,3 @@ async fn main() {
         error!("error: {}", err);
     }
 }
- 
@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use tokio::sync::{Mutex as AsyncMutex, Semaphore};
+
+use crate::connection::Connection;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// A small pool of `Connection`s to a single `(hostname, port)`, modeled on
+/// darkredis's connection pool: connections are opened lazily, up to
+/// `max_size`, and checked-out connections are handed back to the idle queue
+/// when their guard is dropped rather than closed.
+#[derive(Clone)]
+pub struct ConnectionPool {
+    hostname: String,
+    port: u16,
+    idle: Arc<AsyncMutex<VecDeque<Connection>>>,
+    /// Gates concurrent checkouts at `max_size`. Permits are `forget()`-ten
+    /// on acquire and handed back in `release`, so waiting for a slot parks
+    /// the caller instead of busy-polling the idle queue.
+    limit: Arc<Semaphore>,
+}
+
+impl ConnectionPool {
+    pub fn new(hostname: impl Into<String>, port: u16, max_size: usize) -> ConnectionPool {
+        ConnectionPool {
+            hostname: hostname.into(),
+            port,
+            idle: Arc::new(AsyncMutex::new(VecDeque::new())),
+            limit: Arc::new(Semaphore::new(max_size)),
+        }
+    }
+
+    /// Check out a connection: reuse an idle one if there is one, open a new
+    /// one if the pool hasn't reached `max_size` yet, or otherwise wait for
+    /// one to be returned.
+    pub async fn get(&self) -> Result<PooledConnection> {
+        self.limit.acquire().await.forget();
+
+        if let Some(conn) = self.idle.lock().await.pop_front() {
+            return Ok(PooledConnection { conn: Some(conn), pool: self.clone() })
+        }
+
+        match Connection::connect(&self.hostname, self.port).await {
+            Ok(conn) => Ok(PooledConnection { conn: Some(conn), pool: self.clone() }),
+            Err(err) => {
+                self.limit.add_permits(1);
+                Err(err)
+            },
+        }
+    }
+
+    fn release(&self, conn: Connection) {
+        let idle = self.idle.clone();
+        let limit = self.limit.clone();
+        tokio::spawn(async move {
+            idle.lock().await.push_back(conn);
+            limit.add_permits(1);
+        });
+    }
+}
+
+/// A `Connection` checked out of a `ConnectionPool`. Returns to the pool's
+/// idle queue when dropped, rather than being closed.
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    pool: ConnectionPool,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection taken before drop")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection taken before drop")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.release(conn);
+        }
+    }
+}
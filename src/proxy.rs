@@ -0,0 +1,80 @@
+use std::error::Error;
+use std::time::Instant;
+use tokio::net::TcpListener;
+use log::*;
+
+use crate::connection::Connection;
+use crate::redis::RedisValue;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// The name of the command a RESP value encodes, e.g. `"subscribe"` for
+/// `*2\r\n$9\r\nsubscribe\r\n$3\r\nfoo\r\n`, lowercased for matching.
+fn command_name(value: &RedisValue) -> Option<String> {
+    match value {
+        RedisValue::Array(items) => items.first()?.as_str().map(|s| s.to_lowercase()),
+        _ => None,
+    }
+}
+
+/// Proxy one client connection for as long as it stays open: forward every
+/// command to `upstream`, relay its reply back, and keep relaying further
+/// pushes for streaming commands like `monitor`/`subscribe`.
+async fn proxy_client(mut client: Connection, upstream_host: String, upstream_port: u16) -> Result<()> {
+    let mut upstream = Connection::connect(&upstream_host, upstream_port).await?;
+
+    loop {
+        let request = client.next_value().await?;
+        let cmd = command_name(&request);
+
+        let start = Instant::now();
+        upstream.write_raw(request.to_wire()?.as_slice()).await?;
+
+        // Unsolicited RESP3 pushes (pub/sub, client-side caching, ...) can
+        // arrive ahead of the actual reply; relay them and keep waiting
+        // instead of mistaking one for the reply, which would desync the
+        // client from its own request/reply pairing for the rest of the
+        // session.
+        let reply = loop {
+            let value = upstream.next_value().await?;
+            match value {
+                RedisValue::Push(_) => {
+                    debug!("push: {}", value);
+                    client.write_raw(value.to_wire()?.as_slice()).await?;
+                },
+                _ => break value,
+            }
+        };
+        info!("{:?} ({:?}) -> {}", cmd, start.elapsed(), reply);
+        client.write_raw(reply.to_wire()?.as_slice()).await?;
+
+        match cmd.as_deref() {
+            Some("monitor") | Some("subscribe") | Some("psubscribe") => loop {
+                let push = upstream.next_value().await?;
+                debug!("push: {}", push);
+                client.write_raw(push.to_wire()?.as_slice()).await?;
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Bind `listen_addr` and forward every accepted client to
+/// `(upstream_host, upstream_port)`, logging each command/reply pair and
+/// their round-trip time.
+pub async fn run(listen_addr: &str, upstream_host: &str, upstream_port: u16) -> Result<()> {
+    let mut listener = TcpListener::bind(listen_addr).await?;
+    info!("proxying {} -> {}:{}", listen_addr, upstream_host, upstream_port);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        info!("client connected: {}", peer);
+        let upstream_host = upstream_host.to_string();
+
+        tokio::spawn(async move {
+            if let Err(err) = proxy_client(Connection::new(stream), upstream_host, upstream_port).await {
+                info!("client {} disconnected: {}", peer, err);
+            }
+        });
+    }
+}
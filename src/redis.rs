@@ -2,35 +2,93 @@ use std::fmt;
 use std::error::Error;
 use bytes::Bytes;
 
+/// Error from [`RedisValue::deserialize`].
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// Not enough bytes are buffered yet to parse a whole frame; the caller
+    /// should read more bytes and retry with the same buffer contents plus
+    /// whatever came in.
+    Incomplete,
+    /// The bytes buffered do not form a valid RESP frame.
+    Invalid(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Incomplete => write!(f, "incomplete frame"),
+            ParseError::Invalid(msg) => write!(f, "invalid frame: {}", msg),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
 #[derive(Debug)]
 pub enum RedisValue {
     Str(String),
-    Bulk(String),
+    Bulk(Bytes),
     Array(Vec<RedisValue>),
     Int(i64),
     Nil,
     Error(String),
+    // RESP3 additions
+    Double(f64),
+    Boolean(bool),
+    BigNumber(String),
+    Null,
+    /// A verbatim string: `(format, text)`, e.g. `("txt", "some text")`.
+    Verbatim(String, Bytes),
+    Map(Vec<(RedisValue, RedisValue)>),
+    Set(Vec<RedisValue>),
+    /// Out-of-band push message (pub/sub, client-side caching invalidation, ...).
+    /// Kept as its own variant rather than folded into `Array` so callers can
+    /// tell unsolicited server pushes apart from ordinary command replies.
+    Push(Vec<RedisValue>),
 }
 
 impl fmt::Display for RedisValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn fmt_items(f: &mut fmt::Formatter, v: &[RedisValue]) -> fmt::Result {
+            if v.len() == 0 {
+                write!(f, "(empty array)")
+            } else {
+                for i in 1..=v.len() {
+                    write!(f, "{}{}) {}", if i > 1 { "\n" } else { "" },
+                        i, v[i-1])?;
+                }
+                Ok(())
+            }
+        }
+
         match self {
             RedisValue::Str(s) => write!(f, "{}", s),
-            RedisValue::Bulk(s) => write!(f, "{:?}", s),
+            RedisValue::Bulk(b) => write!(f, "{:?}", String::from_utf8_lossy(b)),
             RedisValue::Int(i) => write!(f, "(integer) {}", i),
             RedisValue::Nil => write!(f, "(nil)"),
             RedisValue::Error(s) => write!(f, "(error) {}", s),
-            RedisValue::Array(v) => {
-                if v.len() == 0 {
-                    write!(f, "(empty array)")
+            RedisValue::Array(v) => fmt_items(f, v),
+            RedisValue::Double(d) => write!(f, "(double) {}", d),
+            RedisValue::Boolean(b) => write!(f, "{}", if *b { "(true)" } else { "(false)" }),
+            RedisValue::BigNumber(s) => write!(f, "(big number) {}", s),
+            RedisValue::Null => write!(f, "(nil)"),
+            RedisValue::Verbatim(_, text) => write!(f, "{}", String::from_utf8_lossy(text)),
+            RedisValue::Map(pairs) => {
+                if pairs.len() == 0 {
+                    write!(f, "(empty hash)")
                 } else {
-                    for i in 1..=v.len() {
-                        write!(f, "{}{}) {}", if i > 1 { "\n" } else { "" },
-                            i, v[i-1])?;
+                    for (i, (k, v)) in pairs.iter().enumerate() {
+                        write!(f, "{}{}) {}\n{}) {}", if i > 0 { "\n" } else { "" },
+                            i * 2 + 1, k, i * 2 + 2, v)?;
                     }
                     Ok(())
                 }
             },
+            RedisValue::Set(v) => fmt_items(f, v),
+            RedisValue::Push(v) => {
+                write!(f, "(push) ")?;
+                fmt_items(f, v)
+            },
         }
     }
 }
@@ -39,7 +97,9 @@ impl fmt::Display for RedisValue {
 pub enum Command {
     Get(String),
     Set(String, String),
-    Pipeline(Vec<Command>)
+    Pipeline(Vec<Command>),
+    /// `HELLO [protover]`, used to negotiate RESP3 right after connecting.
+    Hello(Option<u8>),
 }
 
 impl From<Command> for RedisValue {
@@ -47,6 +107,13 @@ impl From<Command> for RedisValue {
         match cmd {
             Command::Get(key) => RedisValue::from_vec(vec![key]),
             Command::Set(key, val) => RedisValue::from_vec(vec![key, val]),
+            Command::Hello(protover) => {
+                let mut args = vec!["HELLO".to_string()];
+                if let Some(protover) = protover {
+                    args.push(protover.to_string());
+                }
+                RedisValue::from_vec(args)
+            },
             _ => {unimplemented!();}
         }
     }
@@ -56,139 +123,202 @@ impl RedisValue {
     pub fn from_vec(v: Vec<String>) -> RedisValue {
         match v.len() {
             0 => RedisValue::Nil,
-            _ => RedisValue::Array(v.into_iter().map(RedisValue::Bulk).collect())
+            _ => RedisValue::Array(v.into_iter()
+                .map(|s| RedisValue::Bulk(Bytes::from(s.into_bytes())))
+                .collect())
         }
     }
 
-    pub fn is_valid<S: AsRef<[u8]>>(s: S) -> bool {
-        if s.as_ref().len() == 0 { return false }
-
-        fn match_string<'a>(ts: &mut impl DoubleEndedIterator<Item = &'a u8>) -> bool {
-            ts.find(|&&c| c == '\n').is_some()
+    /// The raw payload of a `Bulk` or `Verbatim` value, if this is one.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            RedisValue::Bulk(b) => Some(b.as_ref()),
+            RedisValue::Verbatim(_, b) => Some(b.as_ref()),
+            _ => None,
         }
+    }
 
-        fn match_value<'a>(ts: &mut impl DoubleEndedIterator<Item = &'a u8>) -> bool {
-            if let Some(ch) = ts.next() {
-                match ch {
-                    b'-' => {
-                        match_string(ts)
-                    },
-                    b'+' => {
-                        match_string(ts)
-                    },
-                    b'$' => {
-                        let mut n = match_string(ts).parse::<i32>().unwrap_or(0);
-                        if n == -1 {
-                            Some(RedisValue::Nil)
-                        } else {
-                            let mut buf = vec![];
-                            while n > 0 {
-                                let ch = ts.next().expect("invlaid resp");
-                                buf.push(*ch);
-                                n -= 1;
-                            }
-
-                            ts.next();
-                            ts.next();
+    /// A lossy UTF-8 view of a `Bulk` or `Verbatim` value's payload, if this
+    /// is one. Use [`RedisValue::as_bytes`] to get at the exact bytes of a
+    /// binary value instead.
+    pub fn as_str(&self) -> Option<std::borrow::Cow<str>> {
+        self.as_bytes().map(String::from_utf8_lossy)
+    }
 
-                            Some(RedisValue::Bulk(String::from_utf8_lossy(&buf).to_string()))
-                        }
-                    },
-                    b':' => {
-                        Some(RedisValue::Int(match_string(ts).parse::<i64>().unwrap_or(0)))
-                    },
-                    b'*' => {
-                        let n = match_string(ts).parse::<usize>().unwrap_or(0);
-                        let res = (0..n).fold(vec![], |mut v, _| {
-                            let value = match_value(ts).expect("invalid resp");
-                            v.push(value);
-                            v
-                        });
+    /// Deserialize a single `RedisValue` from the front of `s`, returning the
+    /// value together with the number of bytes it consumed.
+    ///
+    /// This never blocks on short input: if `s` holds the start of a frame
+    /// but not all of it yet, it returns `ParseError::Incomplete` so the
+    /// caller can buffer more bytes and retry, rather than panicking on a
+    /// frame that's been split across reads.
+    pub fn deserialize(s: &[u8]) -> Result<(RedisValue, usize), ParseError> {
+        if s.len() == 0 {
+            return Err(ParseError::Incomplete)
+        }
 
-                        Some(RedisValue::Array(res))
-                    },
-                    _ => panic!("invalid redis resp"),
+        // Reads up to the next "\r\n", returning the bytes in between and
+        // advancing `pos` past it. `Incomplete` if no "\r\n" is buffered yet.
+        fn read_line<'a>(s: &'a [u8], pos: &mut usize) -> Result<&'a [u8], ParseError> {
+            let begin = *pos;
+            let mut i = begin;
+            while i < s.len() {
+                if s[i] == b'\r' {
+                    if i + 1 >= s.len() {
+                        return Err(ParseError::Incomplete)
+                    }
+                    *pos = i + 2;
+                    return Ok(&s[begin..i])
                 }
-            } else {
-                None
+                i += 1;
             }
+            Err(ParseError::Incomplete)
         }
 
-        let mut ts = s.iter();
-        match_value(&mut ts).map(|v| (v, s.as_ref().len() - ts.size_hint().0))
-    }
-
-    /// deserialize a RedisValue from `s`, and return value with consumed bytes
-    pub fn deserialize(s: &[u8]) -> Option<(RedisValue, usize)> {
-        if s.as_ref().len() == 0 {
-            return None
+        fn read_string(s: &[u8], pos: &mut usize) -> Result<String, ParseError> {
+            Ok(String::from_utf8_lossy(read_line(s, pos)?).to_string())
         }
 
-        fn match_string<'a>(ts: &mut impl DoubleEndedIterator<Item = &'a u8>) -> String {
-            let mut buf = vec![];
-            while let Some(ch) = ts.next() {
-                if *ch == b'\r' {
-                    break
-                }
-
-                buf.push(*ch);
+        fn read_len(s: &[u8], pos: &mut usize) -> Result<i64, ParseError> {
+            let n = read_string(s, pos)?.parse::<i64>()
+                .map_err(|e| ParseError::Invalid(format!("bad length: {}", e)))?;
+            // -1 is the well-known "null" length; anything else negative is
+            // malformed and must be rejected here, before it's ever cast to
+            // `usize` (which would wrap around into an enormous value).
+            if n < -1 {
+                return Err(ParseError::Invalid(format!("bad length: {}", n)))
             }
-            ts.next(); // eat \n
-            String::from_utf8_lossy(&buf).to_string()
+            Ok(n)
         }
 
-        fn match_value<'a>(ts: &mut impl DoubleEndedIterator<Item = &'a u8>) -> Option<RedisValue> {
-            if let Some(ch) = ts.next() {
-                match ch {
-                    b'-' => {
-                        Some(RedisValue::Error(match_string(ts)))
-                    },
-                    b'+' => {
-                        Some(RedisValue::Str(match_string(ts)))
-                    },
-                    b'$' => {
-                        let mut n = match_string(ts).parse::<i32>().unwrap_or(0);
-                        if n == -1 {
-                            Some(RedisValue::Nil)
-                        } else {
-                            let mut buf = vec![];
-                            while n > 0 {
-                                let ch = ts.next().expect("invlaid resp");
-                                buf.push(*ch);
-                                n -= 1;
-                            }
-
-                            ts.next();
-                            ts.next();
+        // Reads `n` raw bytes followed by a trailing "\r\n".
+        fn read_payload<'a>(s: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8], ParseError> {
+            if *pos + n + 2 > s.len() {
+                return Err(ParseError::Incomplete)
+            }
+            let payload = &s[*pos..*pos + n];
+            *pos += n + 2;
+            Ok(payload)
+        }
 
-                            Some(RedisValue::Bulk(String::from_utf8_lossy(&buf).to_string()))
-                        }
-                    },
-                    b':' => {
-                        Some(RedisValue::Int(match_string(ts).parse::<i64>().unwrap_or(0)))
-                    },
-                    b'*' => {
-                        let n = match_string(ts).parse::<usize>().unwrap_or(0);
-                        let res = (0..n).fold(vec![], |mut v, _| {
-                            let value = match_value(ts).expect("invalid resp");
-                            v.push(value);
-                            v
-                        });
+        fn match_value(s: &[u8], pos: &mut usize) -> Result<RedisValue, ParseError> {
+            if *pos >= s.len() {
+                return Err(ParseError::Incomplete)
+            }
+            let tag = s[*pos];
+            *pos += 1;
 
-                        Some(RedisValue::Array(res))
-                    },
-                    _ => panic!("invalid redis resp"),
-                }
-            } else {
-                None
+            match tag {
+                b'-' => Ok(RedisValue::Error(read_string(s, pos)?)),
+                b'+' => Ok(RedisValue::Str(read_string(s, pos)?)),
+                b'$' => {
+                    let n = read_len(s, pos)?;
+                    if n == -1 {
+                        Ok(RedisValue::Nil)
+                    } else {
+                        let buf = read_payload(s, pos, n as usize)?;
+                        Ok(RedisValue::Bulk(Bytes::copy_from_slice(buf)))
+                    }
+                },
+                b':' => {
+                    let line = read_string(s, pos)?;
+                    Ok(RedisValue::Int(line.parse::<i64>()
+                        .map_err(|e| ParseError::Invalid(format!("bad integer: {}", e)))?))
+                },
+                b'*' => {
+                    let n = read_len(s, pos)?;
+                    if n == -1 {
+                        return Ok(RedisValue::Nil)
+                    }
+                    // Don't pre-reserve from the wire-declared count: it's
+                    // unvalidated and a corrupt or adversarial length would
+                    // otherwise trigger a huge upfront allocation. Grow as
+                    // elements are actually parsed instead.
+                    let mut res = Vec::new();
+                    for _ in 0..n {
+                        res.push(match_value(s, pos)?);
+                    }
+                    Ok(RedisValue::Array(res))
+                },
+                b',' => {
+                    let line = read_string(s, pos)?;
+                    let d = match line.as_str() {
+                        "inf" => f64::INFINITY,
+                        "-inf" => f64::NEG_INFINITY,
+                        "nan" => f64::NAN,
+                        _ => line.parse::<f64>()
+                            .map_err(|e| ParseError::Invalid(format!("bad double: {}", e)))?,
+                    };
+                    Ok(RedisValue::Double(d))
+                },
+                b'#' => {
+                    match read_line(s, pos)? {
+                        b"t" => Ok(RedisValue::Boolean(true)),
+                        b"f" => Ok(RedisValue::Boolean(false)),
+                        other => Err(ParseError::Invalid(format!("bad boolean: {:?}", other))),
+                    }
+                },
+                b'(' => Ok(RedisValue::BigNumber(read_string(s, pos)?)),
+                b'_' => {
+                    read_line(s, pos)?;
+                    Ok(RedisValue::Null)
+                },
+                b'=' => {
+                    let n = read_len(s, pos)?;
+                    if n < 0 {
+                        return Err(ParseError::Invalid(format!("bad verbatim string length: {}", n)))
+                    }
+                    let buf = read_payload(s, pos, n as usize)?;
+                    // first 3 bytes are the format tag (e.g. "txt", "mkd"), then ':'
+                    let format = String::from_utf8_lossy(&buf[..3.min(buf.len())]).to_string();
+                    let text = Bytes::copy_from_slice(&buf[4.min(buf.len())..]);
+                    Ok(RedisValue::Verbatim(format, text))
+                },
+                b'%' => {
+                    let n = read_len(s, pos)?;
+                    if n < 0 {
+                        return Err(ParseError::Invalid(format!("bad map length: {}", n)))
+                    }
+                    let mut res = Vec::new();
+                    for _ in 0..n {
+                        let key = match_value(s, pos)?;
+                        let val = match_value(s, pos)?;
+                        res.push((key, val));
+                    }
+                    Ok(RedisValue::Map(res))
+                },
+                b'~' => {
+                    let n = read_len(s, pos)?;
+                    if n < 0 {
+                        return Err(ParseError::Invalid(format!("bad set length: {}", n)))
+                    }
+                    let mut res = Vec::new();
+                    for _ in 0..n {
+                        res.push(match_value(s, pos)?);
+                    }
+                    Ok(RedisValue::Set(res))
+                },
+                b'>' => {
+                    let n = read_len(s, pos)?;
+                    if n < 0 {
+                        return Err(ParseError::Invalid(format!("bad push length: {}", n)))
+                    }
+                    let mut res = Vec::new();
+                    for _ in 0..n {
+                        res.push(match_value(s, pos)?);
+                    }
+                    Ok(RedisValue::Push(res))
+                },
+                other => Err(ParseError::Invalid(format!("unknown type byte: {}", other as char))),
             }
         }
 
-        let mut ts = s.iter();
-        match_value(&mut ts).map(|v| (v, s.as_ref().len() - ts.size_hint().0))
+        let mut pos = 0;
+        let value = match_value(s, &mut pos)?;
+        Ok((value, pos))
     }
 
-    pub fn to_wire(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+    pub fn to_wire(&self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
         use std::io::Write;
         macro_rules! write_cmd {
             ($res:ident, $e:expr) => (Write::write(&mut $res, $e)?);
@@ -201,11 +331,11 @@ impl RedisValue {
                 write_cmd!(res, s.as_bytes());
                 write_cmd!(res, b"\r\n");
             },
-            RedisValue::Bulk(s) => {
+            RedisValue::Bulk(b) => {
                 write_cmd!(res, b"$");
-                write_cmd!(res, format!("{}", s.len()).as_bytes());
+                write_cmd!(res, format!("{}", b.len()).as_bytes());
                 write_cmd!(res, b"\r\n");
-                write_cmd!(res, s.as_bytes());
+                write_cmd!(res, b.as_ref());
                 write_cmd!(res, b"\r\n");
             },
             RedisValue::Int(i) => {
@@ -231,6 +361,68 @@ impl RedisValue {
                     write_cmd!(res, &d.to_wire()?);
                 }
             },
+            RedisValue::Double(d) => {
+                write_cmd!(res, b",");
+                // Match the literals `deserialize` accepts on the way in:
+                // Rust's Display renders these as "inf"/"-inf"/"NaN", but
+                // RESP3 requires lowercase.
+                let rendered = if d.is_nan() {
+                    "nan".to_string()
+                } else if *d == f64::INFINITY {
+                    "inf".to_string()
+                } else if *d == f64::NEG_INFINITY {
+                    "-inf".to_string()
+                } else {
+                    format!("{}", d)
+                };
+                write_cmd!(res, rendered.as_bytes());
+                write_cmd!(res, b"\r\n");
+            },
+            RedisValue::Boolean(b) => {
+                write_cmd!(res, if *b { b"#t\r\n" } else { b"#f\r\n" });
+            },
+            RedisValue::BigNumber(s) => {
+                write_cmd!(res, b"(");
+                write_cmd!(res, s.as_bytes());
+                write_cmd!(res, b"\r\n");
+            },
+            RedisValue::Null => {
+                write_cmd!(res, b"_\r\n");
+            },
+            RedisValue::Verbatim(format, text) => {
+                let mut body = format!("{}:", format).into_bytes();
+                body.extend_from_slice(text.as_ref());
+                write_cmd!(res, b"=");
+                write_cmd!(res, format!("{}", body.len()).as_bytes());
+                write_cmd!(res, b"\r\n");
+                write_cmd!(res, body.as_slice());
+                write_cmd!(res, b"\r\n");
+            },
+            RedisValue::Map(pairs) => {
+                write_cmd!(res, b"%");
+                write_cmd!(res, format!("{}", pairs.len()).as_bytes());
+                write_cmd!(res, b"\r\n");
+                for (k, v) in pairs {
+                    write_cmd!(res, &k.to_wire()?);
+                    write_cmd!(res, &v.to_wire()?);
+                }
+            },
+            RedisValue::Set(v) => {
+                write_cmd!(res, b"~");
+                write_cmd!(res, format!("{}", v.len()).as_bytes());
+                write_cmd!(res, b"\r\n");
+                for d in v {
+                    write_cmd!(res, &d.to_wire()?);
+                }
+            },
+            RedisValue::Push(v) => {
+                write_cmd!(res, b">");
+                write_cmd!(res, format!("{}", v.len()).as_bytes());
+                write_cmd!(res, b"\r\n");
+                for d in v {
+                    write_cmd!(res, &d.to_wire()?);
+                }
+            },
         }
 
         Ok(res)
@@ -260,7 +452,7 @@ mod tests {
     }
     #[test]
     fn test_to_wire_bulk() {
-        let r = RedisValue::Bulk("hello".to_string());
+        let r = RedisValue::Bulk(Bytes::from_static(b"hello"));
         assert_eq!(&r.to_wire().expect(""), b"$5\r\nhello\r\n");
     }
     #[test]
@@ -274,24 +466,87 @@ mod tests {
         v.push(RedisValue::Error("hello".to_string()));
         v.push(RedisValue::Nil);
         v.push(RedisValue::Str("hello".to_string()));
-        v.push(RedisValue::Bulk("hello".to_string()));
+        v.push(RedisValue::Bulk(Bytes::from_static(b"hello")));
         v.push(RedisValue::Int(34));
         let r = RedisValue::Array(v);
         let r2 = b"*5\r\n-hello\r\n$-1\r\n+hello\r\n$5\r\nhello\r\n:34\r\n".iter().map(|&c| c).collect::<Vec<u8>>();
         assert_eq!(r.to_wire().expect(""), r2);
     }
     #[test]
+    fn test_to_wire_double() {
+        let r = RedisValue::Double(3.14);
+        assert_eq!(&r.to_wire().expect(""), b",3.14\r\n");
+    }
+    #[test]
+    fn test_to_wire_double_special_values() {
+        assert_eq!(&RedisValue::Double(f64::INFINITY).to_wire().expect(""), b",inf\r\n");
+        assert_eq!(&RedisValue::Double(f64::NEG_INFINITY).to_wire().expect(""), b",-inf\r\n");
+        assert_eq!(&RedisValue::Double(f64::NAN).to_wire().expect(""), b",nan\r\n");
+    }
+    #[test]
+    fn test_to_wire_boolean() {
+        assert_eq!(&RedisValue::Boolean(true).to_wire().expect(""), b"#t\r\n");
+        assert_eq!(&RedisValue::Boolean(false).to_wire().expect(""), b"#f\r\n");
+    }
+    #[test]
+    fn test_to_wire_big_number() {
+        let r = RedisValue::BigNumber("3492890328409238509324850943850943825024385".to_string());
+        assert_eq!(&r.to_wire().expect(""), b"(3492890328409238509324850943850943825024385\r\n");
+    }
+    #[test]
+    fn test_to_wire_null() {
+        assert_eq!(&RedisValue::Null.to_wire().expect(""), b"_\r\n");
+    }
+    #[test]
+    fn test_to_wire_verbatim() {
+        let r = RedisValue::Verbatim("txt".to_string(), Bytes::from_static(b"Some string"));
+        assert_eq!(&r.to_wire().expect(""), b"=15\r\ntxt:Some string\r\n");
+    }
+    #[test]
+    fn test_to_wire_map() {
+        let r = RedisValue::Map(vec![(RedisValue::Str("key".to_string()), RedisValue::Int(1))]);
+        assert_eq!(&r.to_wire().expect(""), b"%1\r\n+key\r\n:1\r\n");
+    }
+    #[test]
+    fn test_to_wire_set() {
+        let r = RedisValue::Set(vec![RedisValue::Int(1), RedisValue::Int(2)]);
+        assert_eq!(&r.to_wire().expect(""), b"~2\r\n:1\r\n:2\r\n");
+    }
+    #[test]
+    fn test_to_wire_push() {
+        let r = RedisValue::Push(vec![RedisValue::Str("message".to_string())]);
+        assert_eq!(&r.to_wire().expect(""), b">1\r\n+message\r\n");
+    }
+    #[test]
+    fn test_deserialize_double_nan_round_trip() {
+        let data = ",nan\r\n";
+        let value = RedisValue::deserialize(data.as_bytes()).expect("");
+        match value.0 {
+            RedisValue::Double(d) => assert!(d.is_nan()),
+            other => panic!("expected Double, got {:?}", other),
+        }
+        assert_eq!(value.0.to_wire().expect(""), data.as_bytes());
+    }
+    #[test]
     fn test_deserialize() {
         let data = "*5\r\n-hello\r\n$-1\r\n+hello\r\n$5\r\nhello\r\n:34\r\n";
-        let value = RedisValue::deserialize(data).expect("");
+        let value = RedisValue::deserialize(data.as_bytes()).expect("");
         println!("{}", value.0);
         assert_eq!(value.0.to_wire().expect(""), data.as_bytes());
     }
     #[test]
     fn test_deserialize2() {
         let data = "*5\r\n-hello\r\n$-1\r\n+hello\r\n$5\r\nhello\r\n:34\r\n+another value";
-        let value = RedisValue::deserialize(data).expect("");
+        let value = RedisValue::deserialize(data.as_bytes()).expect("");
         println!("{}, {}", value.0, value.1);
         assert_eq!(value.0.to_wire().expect(""), &data.as_bytes()[..value.1]);
     }
+    #[test]
+    fn test_deserialize_incomplete_returns_incomplete_not_panic() {
+        let data = b"$5\r\nhel";
+        assert_eq!(RedisValue::deserialize(data).unwrap_err(), ParseError::Incomplete);
+
+        let data = b"*2\r\n$3\r\nfoo\r\n$3\r\nba";
+        assert_eq!(RedisValue::deserialize(data).unwrap_err(), ParseError::Incomplete);
+    }
 }